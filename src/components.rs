@@ -1,10 +1,12 @@
 use core::future::Future;
-use core::mem::MaybeUninit;
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use embassy::channel::signal::Signal;
-use embassy::traits::gpio::WaitForAnyEdge;
+use embassy::traits::gpio::{WaitForAnyEdge, WaitForFallingEdge, WaitForRisingEdge};
+use std::collections::VecDeque;
+use std::sync::{Mutex, RwLock};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
 
 pub struct WebLed {
     pin: &'static OutputPin,
@@ -29,6 +31,55 @@ impl embedded_hal::digital::v2::OutputPin for WebLed {
     }
 }
 
+/// A dimmable LED driven by a [`PwmOutputPin`] instead of the binary
+/// [`OutputPin`] that backs [`WebLed`].
+pub struct WebPwmLed {
+    pin: &'static PwmOutputPin,
+    max_duty: u16,
+    duty: u16,
+    enabled: bool,
+}
+
+impl WebPwmLed {
+    pub fn new(pin: &'static PwmOutputPin, max_duty: u16) -> Self {
+        Self {
+            pin,
+            max_duty,
+            duty: 0,
+            enabled: true,
+        }
+    }
+}
+
+impl embedded_hal::PwmPin for WebPwmLed {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.pin.set_duty(0);
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        self.pin.set_duty(self.duty);
+    }
+
+    fn get_duty(&self) -> Self::Duty {
+        self.duty
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+        self.max_duty
+    }
+
+    fn set_duty(&mut self, duty: Self::Duty) {
+        self.duty = duty.min(self.max_duty);
+        if self.enabled {
+            self.pin.set_duty(self.duty);
+        }
+    }
+}
+
 pub struct WebButton {
     pin: &'static InputPin,
 }
@@ -56,6 +107,20 @@ impl WaitForAnyEdge for WebButton {
     }
 }
 
+impl WaitForRisingEdge for WebButton {
+    type Future<'a> = WaitForLevelFuture<'a>;
+    fn wait_for_rising_edge<'a>(&'a mut self) -> Self::Future<'a> {
+        self.pin.wait_for(true)
+    }
+}
+
+impl WaitForFallingEdge for WebButton {
+    type Future<'a> = WaitForLevelFuture<'a>;
+    fn wait_for_falling_edge<'a>(&'a mut self) -> Self::Future<'a> {
+        self.pin.wait_for(false)
+    }
+}
+
 pub struct InputPin {
     high: AtomicBool,
     signal: Signal<()>,
@@ -69,7 +134,15 @@ impl InputPin {
         }
     }
 
-    pub fn configure(&'static mut self, element: &str) {
+    /// Creates and configures a new pin at runtime, rather than requiring
+    /// a `static` item. The pin is leaked for the lifetime of the program.
+    pub fn create(element: &str) -> &'static InputPin {
+        let pin: &'static InputPin = Box::leak(Box::new(InputPin::new()));
+        pin.configure(element);
+        pin
+    }
+
+    pub fn configure(&'static self, element: &str) {
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
 
@@ -90,6 +163,44 @@ impl InputPin {
         cb.forget();
     }
 
+    /// Drives this pin from the keyboard in addition to the click handler
+    /// registered by [`InputPin::configure`]. Pressing `key` stores `false`
+    /// (mirroring a press) and releasing it stores `true` (mirroring a
+    /// release), each time signalling [`WaitForAnyEdge`] waiters.
+    pub fn configure_keyboard(&'static self, key: &str) {
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let key = key.to_string();
+
+        let pin: &'static InputPin = self;
+        let down_key = key.clone();
+        let keydown = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            if event.repeat() {
+                return;
+            }
+            if event.key() == down_key {
+                pin.high.store(false, Ordering::Release);
+                pin.signal.signal(());
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        let keyup = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            if event.key() == key {
+                pin.high.store(true, Ordering::Release);
+                pin.signal.signal(());
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        document
+            .add_event_listener_with_callback("keydown", keydown.as_ref().unchecked_ref())
+            .expect("error adding event listener");
+        document
+            .add_event_listener_with_callback("keyup", keyup.as_ref().unchecked_ref())
+            .expect("error adding event listener");
+        keydown.forget();
+        keyup.forget();
+    }
+
     fn get_value(&self) -> bool {
         self.high.load(Ordering::Acquire)
     }
@@ -98,39 +209,141 @@ impl InputPin {
         self.signal.reset();
         SignalFuture::new(&self.signal)
     }
+
+    /// Waits until the pin's level matches `target`, ignoring transitions
+    /// to the other level. Built on the same `Signal` as [`InputPin::wait`],
+    /// this is what gives [`WebButton`] precise rising/falling-edge futures
+    /// instead of only [`WaitForAnyEdge`].
+    fn wait_for<'a>(&'a self, target: bool) -> WaitForLevelFuture<'a> {
+        self.signal.reset();
+        WaitForLevelFuture { pin: self, target }
+    }
+}
+
+/// Marker type identifying the (single, simulated) analog-to-digital
+/// converter that [`WebAnalogInput`] reads through.
+pub struct WebAdc;
+
+/// An analog input pin backed by an `<input type="range">` element.
+///
+/// Each `input` event on the slider scales its value into the full-scale
+/// `max` given to [`WebAnalogInput::new`]; reading the pin returns the value
+/// stored by the last such event (`0` before the first one), mirroring how
+/// [`InputPin`] simulates a digital pin with a button.
+pub struct WebAnalogInput {
+    value: AtomicU32,
+    max: u16,
+    signal: Signal<()>,
+}
+
+impl WebAnalogInput {
+    /// `max` is the full-scale value reported for the top of the slider's
+    /// range, e.g. `4095` for a 12-bit ADC.
+    pub const fn new(max: u16) -> Self {
+        Self {
+            value: AtomicU32::new(0),
+            max,
+            signal: Signal::new(),
+        }
+    }
+
+    /// Creates and configures a new pin at runtime, rather than requiring
+    /// a `static` item. The pin is leaked for the lifetime of the program.
+    pub fn create(element: &str, max: u16) -> &'static WebAnalogInput {
+        let pin: &'static WebAnalogInput = Box::leak(Box::new(WebAnalogInput::new(max)));
+        pin.configure(element);
+        pin
+    }
+
+    pub fn configure(&'static self, element: &str) {
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+
+        let val = document
+            .get_element_by_id(element)
+            .expect("unable to find element");
+        let input: HtmlInputElement = val.clone().dyn_into().expect("element is not an <input>");
+
+        let cb = Closure::wrap(Box::new(move || {
+            let min = input.min().parse::<f64>().unwrap_or(0.0);
+            let range = input.max().parse::<f64>().unwrap_or(100.0) - min;
+            let fraction = if range > 0.0 {
+                (input.value_as_number() - min) / range
+            } else {
+                0.0
+            };
+            let scaled = (fraction.clamp(0.0, 1.0) * self.max as f64).round() as u32;
+            self.value.store(scaled, Ordering::Release);
+            self.signal.signal(());
+        }) as Box<dyn FnMut()>);
+
+        val.add_event_listener_with_callback("input", cb.as_ref().unchecked_ref())
+            .expect("error adding event listener");
+        cb.forget();
+    }
+
+    fn get_value(&self) -> u16 {
+        self.value.load(Ordering::Acquire) as u16
+    }
+
+    /// Waits for the next slider change, mirroring [`InputPin::wait`].
+    pub fn wait<'a>(&'a self) -> SignalFuture<'a, ()> {
+        self.signal.reset();
+        SignalFuture::new(&self.signal)
+    }
+}
+
+impl embedded_hal::adc::Channel<WebAdc> for WebAnalogInput {
+    type ID = ();
+
+    fn channel() -> Self::ID {}
+}
+
+impl embedded_hal::adc::OneShot<WebAdc, u16, WebAnalogInput> for WebAnalogInput {
+    type Error = ();
+
+    fn read(&mut self, pin: &mut WebAnalogInput) -> nb::Result<u16, Self::Error> {
+        Ok(pin.get_value())
+    }
 }
 
 pub struct OutputPin {
     high: AtomicBool,
-    element: MaybeUninit<&'static str>,
-    transform: MaybeUninit<fn(bool) -> OutputVisual>,
+    element: RwLock<Option<&'static str>>,
+    transform: RwLock<Option<fn(bool) -> OutputVisual>>,
 }
 
 impl OutputPin {
     pub const fn new() -> Self {
         Self {
             high: AtomicBool::new(false),
-            element: MaybeUninit::uninit(),
-            transform: MaybeUninit::uninit(),
+            element: RwLock::new(None),
+            transform: RwLock::new(None),
         }
     }
 
-    pub fn configure(
-        &'static mut self,
+    /// Creates and configures a new pin at runtime, rather than requiring
+    /// a `static` item. The pin is leaked for the lifetime of the program.
+    pub fn create(
         element: &'static str,
         transform: fn(bool) -> OutputVisual,
-    ) {
-        unsafe {
-            let p = self.element.as_mut_ptr();
-            p.write(element);
-            let f = self.transform.as_mut_ptr();
-            f.write(transform);
-        };
+    ) -> &'static OutputPin {
+        let pin: &'static OutputPin = Box::leak(Box::new(OutputPin::new()));
+        pin.configure(element, transform);
+        pin
+    }
+
+    /// Binds this pin to `element`, rendering through `transform`. Can be
+    /// called again to reconfigure an already-configured pin.
+    pub fn configure(&'static self, element: &'static str, transform: fn(bool) -> OutputVisual) {
+        *self.element.write().unwrap() = Some(element);
+        *self.transform.write().unwrap() = Some(transform);
         self.set_value(false);
     }
 
     pub fn set_value(&self, high: bool) {
-        let (element, transform) = unsafe { (&*self.element.as_ptr(), &*self.transform.as_ptr()) };
+        let element = self.element.read().unwrap().expect("pin not configured");
+        let transform = self.transform.read().unwrap().expect("pin not configured");
         self.high.store(high, Ordering::Release);
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
@@ -140,6 +353,193 @@ impl OutputPin {
     }
 }
 
+/// A PWM-capable counterpart to [`OutputPin`], rendering a duty fraction
+/// instead of a plain on/off state.
+pub struct PwmOutputPin {
+    duty: AtomicU32,
+    element: RwLock<Option<&'static str>>,
+    transform: RwLock<Option<fn(u16) -> OutputVisual>>,
+}
+
+impl PwmOutputPin {
+    pub const fn new() -> Self {
+        Self {
+            duty: AtomicU32::new(0),
+            element: RwLock::new(None),
+            transform: RwLock::new(None),
+        }
+    }
+
+    /// Creates and configures a new pin at runtime, rather than requiring
+    /// a `static` item. The pin is leaked for the lifetime of the program.
+    pub fn create(
+        element: &'static str,
+        transform: fn(u16) -> OutputVisual,
+    ) -> &'static PwmOutputPin {
+        let pin: &'static PwmOutputPin = Box::leak(Box::new(PwmOutputPin::new()));
+        pin.configure(element, transform);
+        pin
+    }
+
+    /// Binds this pin to `element`, rendering through `transform`. Can be
+    /// called again to reconfigure an already-configured pin.
+    pub fn configure(&'static self, element: &'static str, transform: fn(u16) -> OutputVisual) {
+        *self.element.write().unwrap() = Some(element);
+        *self.transform.write().unwrap() = Some(transform);
+        self.set_duty(0);
+    }
+
+    pub fn set_duty(&self, duty: u16) {
+        let element = self.element.read().unwrap().expect("pin not configured");
+        let transform = self.transform.read().unwrap().expect("pin not configured");
+        self.duty.store(duty as u32, Ordering::Release);
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let txt = document.get_element_by_id(element).unwrap();
+        let output = transform(duty);
+        txt.set_inner_html(output.as_ref());
+    }
+
+    pub fn get_duty(&self) -> u16 {
+        self.duty.load(Ordering::Acquire) as u16
+    }
+}
+
+/// A virtual UART: writes append to an output element's inner HTML, reads
+/// drain a byte queue fed by an input element (or by JS via
+/// [`WebSerial::push_bytes`]).
+#[wasm_bindgen]
+pub struct WebSerial {
+    rx_queue: Mutex<VecDeque<u8>>,
+    rx_signal: Signal<()>,
+    tx_element: RwLock<Option<&'static str>>,
+    tx_buf: Mutex<Vec<u8>>,
+}
+
+impl WebSerial {
+    pub const fn new() -> Self {
+        Self {
+            rx_queue: Mutex::new(VecDeque::new()),
+            rx_signal: Signal::new(),
+            tx_element: RwLock::new(None),
+            tx_buf: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Creates and configures a new serial port at runtime, rather than
+    /// requiring a `static` item. The port is leaked for the lifetime of
+    /// the program.
+    pub fn create(input_element: &str, output_element: &'static str) -> &'static WebSerial {
+        let serial: &'static WebSerial = Box::leak(Box::new(WebSerial::new()));
+        serial.configure(input_element, output_element);
+        serial
+    }
+
+    /// Binds this port to `input_element`/`output_element`. Calling it again
+    /// replaces the output element; input elements bound by earlier calls
+    /// stay bound and keep feeding the receive queue.
+    pub fn configure(&'static self, input_element: &str, output_element: &'static str) {
+        *self.tx_element.write().unwrap() = Some(output_element);
+
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+
+        let val = document
+            .get_element_by_id(input_element)
+            .expect("unable to find element");
+        let input: HtmlInputElement = val.clone().dyn_into().expect("element is not an <input>");
+
+        let cb = Closure::wrap(Box::new(move || {
+            let text = input.value();
+            input.set_value("");
+            self.rx_queue.lock().unwrap().extend(text.into_bytes());
+            self.rx_signal.signal(());
+        }) as Box<dyn FnMut()>);
+
+        val.add_event_listener_with_callback("input", cb.as_ref().unchecked_ref())
+            .expect("error adding event listener");
+        cb.forget();
+    }
+
+    /// Waits for and pops the next received byte, resolving as soon as it
+    /// becomes available instead of blocking on [`embedded_hal::serial::Read`].
+    pub async fn read_byte(&'static self) -> u8 {
+        loop {
+            if let Some(b) = self.rx_queue.lock().unwrap().pop_front() {
+                return b;
+            }
+            self.rx_signal.reset();
+            SignalFuture::new(&self.rx_signal).await;
+        }
+    }
+
+    fn write_str(&self, s: &str) {
+        let element = self
+            .tx_element
+            .read()
+            .unwrap()
+            .expect("serial not configured");
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let el = document.get_element_by_id(element).unwrap();
+        let existing = el.inner_html();
+        el.set_inner_html(&(existing + s));
+    }
+}
+
+#[wasm_bindgen]
+impl WebSerial {
+    /// Lets JavaScript (or a web worker forwarding a `MessageEvent`) inject
+    /// bytes as though they had arrived over the wire.
+    #[wasm_bindgen(js_name = pushBytes)]
+    pub fn push_bytes(&self, bytes: &[u8]) {
+        self.rx_queue.lock().unwrap().extend(bytes.iter().copied());
+        self.rx_signal.signal(());
+    }
+}
+
+impl embedded_hal::serial::Read<u8> for WebSerial {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.rx_queue
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl embedded_hal::serial::Write<u8> for WebSerial {
+    type Error = ();
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        let mut buf = self.tx_buf.lock().unwrap();
+        buf.push(byte);
+        match core::str::from_utf8(&buf) {
+            Ok(s) => {
+                self.write_str(s);
+                buf.clear();
+            }
+            // An incomplete multi-byte sequence at the end of `buf`; wait
+            // for the remaining bytes before decoding.
+            Err(e) if e.error_len().is_none() => {}
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                if valid_up_to > 0 {
+                    self.write_str(core::str::from_utf8(&buf[..valid_up_to]).unwrap());
+                }
+                buf.drain(..valid_up_to.max(1));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
 pub struct SignalFuture<'s, T: Send> {
     signal: &'s Signal<T>,
 }
@@ -161,6 +561,36 @@ impl<T: Send> Future for SignalFuture<'_, T> {
     }
 }
 
+/// Resolves the next time an [`InputPin`]'s level matches `target`, built by
+/// [`InputPin::wait_for`]. Transitions to the other level reset the signal
+/// and keep waiting rather than resolving.
+pub struct WaitForLevelFuture<'a> {
+    pin: &'a InputPin,
+    target: bool,
+}
+
+impl Future for WaitForLevelFuture<'_> {
+    type Output = ();
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        match self.pin.signal.poll_wait(cx) {
+            core::task::Poll::Ready(()) => {
+                if self.pin.get_value() == self.target {
+                    core::task::Poll::Ready(())
+                } else {
+                    self.pin.signal.reset();
+                    cx.waker().wake_by_ref();
+                    core::task::Poll::Pending
+                }
+            }
+            core::task::Poll::Pending => core::task::Poll::Pending,
+        }
+    }
+}
+
 /// A led color.
 ///
 /// Can be converted into a output visual by adding the boolean state:
@@ -197,6 +627,9 @@ impl std::ops::Add<bool> for LedColor {
 pub enum OutputVisual {
     String(&'static str),
     Led(LedColor, bool),
+    /// An LED rendered at a fractional brightness, produced by
+    /// [`OutputVisual::pwm_led`].
+    PwmLed(String),
 }
 
 macro_rules! to_led {
@@ -216,6 +649,13 @@ macro_rules! to_led {
             ),
         }
     };
+    (pwm $on:expr, $duty:expr, $max_duty:expr) => {
+        format!(
+            r#"<svg height="50" width="50"><circle cx="25" cy="25" r="10" fill="{}" fill-opacity="{:.3}"/></svg>"#,
+            $on,
+            $duty as f32 / $max_duty.max(1) as f32
+        )
+    };
 }
 
 impl AsRef<str> for OutputVisual {
@@ -229,10 +669,27 @@ impl AsRef<str> for OutputVisual {
                 LedColor::Orange => to_led!(state, "orange", "#a97200"),
                 LedColor::Blue => to_led!(state, "lightblue", "darkblue"),
             },
+            Self::PwmLed(s) => s,
         }
     }
 }
 
+impl OutputVisual {
+    /// Renders `color` dimmed to `duty / max_duty`, for use inside a
+    /// `fn(u16) -> OutputVisual` transformer passed to
+    /// [`PwmOutputPin::configure`].
+    pub fn pwm_led(color: LedColor, duty: u16, max_duty: u16) -> Self {
+        let on = match color {
+            LedColor::Red => "red",
+            LedColor::Green => "lightgreen",
+            LedColor::Yellow => "yellow",
+            LedColor::Orange => "orange",
+            LedColor::Blue => "lightblue",
+        };
+        OutputVisual::PwmLed(to_led!(pwm on, duty, max_duty))
+    }
+}
+
 impl From<&'static str> for OutputVisual {
     fn from(s: &'static str) -> Self {
         OutputVisual::String(s)